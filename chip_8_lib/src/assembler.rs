@@ -0,0 +1,161 @@
+//! The inverse of `disassembler::disassemble`: turns a line of mnemonic text
+//! (exactly what `disassemble` prints) back into a 16-bit opcode, so a
+//! hand-edited disassembly view can be reloaded into `Cpu::memory`.
+
+/// Splits a mnemonic line into tokens, treating `{`, `}` and `,` as
+/// whitespace so the braces/commas `disassemble` sprinkles into some
+/// mnemonics (e.g. `"SHR V3 {, V5}"`, `"JP V0, 512"`) don't need special
+/// casing here.
+fn tokens(line: &str) -> Vec<String> {
+    line.replace(['{', '}', ','], " ")
+        .split_whitespace()
+        .map(|s| s.to_uppercase())
+        .collect()
+}
+
+fn parse_register(tok: &str) -> Result<usize, String> {
+    let digits = tok
+        .strip_prefix('V')
+        .ok_or_else(|| format!("expected a register like V3, got \"{}\"", tok))?;
+    let reg: usize = digits
+        .parse()
+        .map_err(|_| format!("invalid register \"{}\"", tok))?;
+    if reg > 0xF {
+        return Err(format!("register V{} out of range", reg));
+    }
+    Ok(reg)
+}
+
+fn is_register(tok: &str) -> bool {
+    parse_register(tok).is_ok()
+}
+
+fn parse_value(tok: &str) -> Result<u16, String> {
+    if let Some(hex) = tok.strip_prefix("0X") {
+        u16::from_str_radix(hex, 16).map_err(|_| format!("invalid hex value \"{}\"", tok))
+    } else {
+        tok.parse().map_err(|_| format!("invalid value \"{}\"", tok))
+    }
+}
+
+/// Parses one disassembled line (e.g. `"JP 0x200"`, `"LD V3 45"`,
+/// `"DRW V0 V1 5"`) back into its 16-bit opcode.
+pub fn assemble(line: &str) -> Result<u16, String> {
+    let tok = tokens(line);
+    let parts: Vec<&str> = tok.iter().map(|s| s.as_str()).collect();
+
+    match parts.as_slice() {
+        ["CLS"] => Ok(0x00E0),
+        ["RET"] => Ok(0x00EE),
+
+        ["JP", "V0", addr] => Ok(0xB000 | parse_value(addr)?),
+        ["JP", addr] => Ok(0x1000 | parse_value(addr)?),
+        ["CALL", addr] => Ok(0x2000 | parse_value(addr)?),
+
+        ["SE", x, y] if is_register(y) => Ok(0x5000 | ((parse_register(x)? as u16) << 8) | ((parse_register(y)? as u16) << 4)),
+        ["SE", x, kk] => Ok(0x3000 | ((parse_register(x)? as u16) << 8) | parse_value(kk)?),
+        ["SNE", x, y] if is_register(y) => Ok(0x9000 | ((parse_register(x)? as u16) << 8) | ((parse_register(y)? as u16) << 4)),
+        ["SNE", x, kk] => Ok(0x4000 | ((parse_register(x)? as u16) << 8) | parse_value(kk)?),
+
+        ["OR", x, y] => Ok(0x8001 | ((parse_register(x)? as u16) << 8) | ((parse_register(y)? as u16) << 4)),
+        ["AND", x, y] => Ok(0x8002 | ((parse_register(x)? as u16) << 8) | ((parse_register(y)? as u16) << 4)),
+        ["XOR", x, y] => Ok(0x8003 | ((parse_register(x)? as u16) << 8) | ((parse_register(y)? as u16) << 4)),
+        ["SUB", x, y] => Ok(0x8005 | ((parse_register(x)? as u16) << 8) | ((parse_register(y)? as u16) << 4)),
+        ["SHR", x, y] => Ok(0x8006 | ((parse_register(x)? as u16) << 8) | ((parse_register(y)? as u16) << 4)),
+        ["SUBN", x, y] => Ok(0x8007 | ((parse_register(x)? as u16) << 8) | ((parse_register(y)? as u16) << 4)),
+        ["SHL", x, y] => Ok(0x800E | ((parse_register(x)? as u16) << 8) | ((parse_register(y)? as u16) << 4)),
+
+        ["ADD", "I", x] => Ok(0xF01E | ((parse_register(x)? as u16) << 8)),
+        ["ADD", x, y] if is_register(y) => Ok(0x8004 | ((parse_register(x)? as u16) << 8) | ((parse_register(y)? as u16) << 4)),
+        ["ADD", x, kk] => Ok(0x7000 | ((parse_register(x)? as u16) << 8) | parse_value(kk)?),
+
+        ["LD", "I", addr] => Ok(0xA000 | parse_value(addr)?),
+        ["LD", "DT", x] => Ok(0xF015 | ((parse_register(x)? as u16) << 8)),
+        ["LD", "ST", x] => Ok(0xF018 | ((parse_register(x)? as u16) << 8)),
+        ["LD", "F", x] => Ok(0xF029 | ((parse_register(x)? as u16) << 8)),
+        ["LD", "B", x] => Ok(0xF033 | ((parse_register(x)? as u16) << 8)),
+        ["LD", "[I]", x] => Ok(0xF055 | ((parse_register(x)? as u16) << 8)),
+        ["LD", x, "[I]"] => Ok(0xF065 | ((parse_register(x)? as u16) << 8)),
+        ["LD", x, "DT"] => Ok(0xF007 | ((parse_register(x)? as u16) << 8)),
+        ["LD", x, "K"] => Ok(0xF00A | ((parse_register(x)? as u16) << 8)),
+        ["LD", x, y] if is_register(y) => Ok(0x8000 | ((parse_register(x)? as u16) << 8) | ((parse_register(y)? as u16) << 4)),
+        ["LD", x, kk] => Ok(0x6000 | ((parse_register(x)? as u16) << 8) | parse_value(kk)?),
+
+        ["RND", x, kk] => Ok(0xC000 | ((parse_register(x)? as u16) << 8) | parse_value(kk)?),
+        ["DRW", x, y, n] => Ok(0xD000
+            | ((parse_register(x)? as u16) << 8)
+            | ((parse_register(y)? as u16) << 4)
+            | (parse_value(n)? & 0x000F)),
+
+        ["SKP", x] => Ok(0xE09E | ((parse_register(x)? as u16) << 8)),
+        ["SKNP", x] => Ok(0xE0A1 | ((parse_register(x)? as u16) << 8)),
+
+        _ => Err(format!("unrecognized instruction: \"{}\"", line)),
+    }
+}
+
+/// Assembles a whole program, one instruction per line. Blank lines and
+/// lines that fail to parse are skipped so a debugger view with stray
+/// comments or a trailing blank line still loads.
+pub fn assemble_program(src: &str) -> Vec<u8> {
+    let mut bytes = Vec::new();
+
+    for line in src.lines() {
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+
+        if let Ok(opcode) = assemble(line) {
+            bytes.push((opcode >> 8) as u8);
+            bytes.push((opcode & 0x00FF) as u8);
+        }
+    }
+
+    bytes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::assemble;
+    use crate::disassembler::disassemble;
+
+    #[test]
+    fn round_trips_every_opcode() {
+        let mut opcodes: Vec<u16> = Vec::new();
+
+        for x in 0..16u16 {
+            for y in 0..16u16 {
+                opcodes.push(0x5000 | (x << 8) | (y << 4)); // SE Vx, Vy
+                opcodes.push(0x9000 | (x << 8) | (y << 4)); // SNE Vx, Vy
+                for op in [0x0, 0x1, 0x2, 0x3, 0x4, 0x5, 0x6, 0x7, 0xE] {
+                    opcodes.push(0x8000 | (x << 8) | (y << 4) | op);
+                }
+            }
+            opcodes.push(0x1000 | x); // JP addr
+            opcodes.push(0x2000 | x); // CALL addr
+            opcodes.push(0x3000 | (x << 8) | 0x45); // SE Vx, byte
+            opcodes.push(0x4000 | (x << 8) | 0x45); // SNE Vx, byte
+            opcodes.push(0x6000 | (x << 8) | 0x45); // LD Vx, byte
+            opcodes.push(0x7000 | (x << 8) | 0x45); // ADD Vx, byte
+            opcodes.push(0xA000 | x); // LD I, addr
+            opcodes.push(0xB000 | x); // JP V0, addr
+            opcodes.push(0xC000 | (x << 8) | 0x45); // RND Vx, byte
+            opcodes.push(0xD000 | (x << 8) | (0x5 << 4) | 0x5); // DRW Vx, Vy, n
+            opcodes.push(0xE09E | (x << 8)); // SKP Vx
+            opcodes.push(0xE0A1 | (x << 8)); // SKNP Vx
+            for f in [0x07, 0x0A, 0x15, 0x18, 0x1E, 0x29, 0x33, 0x55, 0x65] {
+                opcodes.push(0xF000 | (x << 8) | f);
+            }
+        }
+        opcodes.push(0x00E0); // CLS
+        opcodes.push(0x00EE); // RET
+
+        for opcode in opcodes {
+            let mnemonic = disassemble(opcode);
+            let reassembled = assemble(&mnemonic)
+                .unwrap_or_else(|e| panic!("failed to assemble \"{}\" (from 0x{:X}): {}", mnemonic, opcode, e));
+            assert_eq!(reassembled, opcode, "round trip mismatch for \"{}\"", mnemonic);
+        }
+    }
+}
@@ -0,0 +1,104 @@
+//! Pre-decoding support for the `block_cache` feature (see `Cpu::emulate_cycle`).
+//!
+//! `decode` turns a raw opcode into a `DecodedOp` once; `Cpu` caches the
+//! result by address so a later cycle that revisits the same `pc` (the
+//! common case — loops are most of a CHIP-8 program's runtime) replays it
+//! without re-fetching/re-masking the bits again.
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum DecodedOp {
+    Cls,
+    Ret,
+    Sys,
+    Jp(u16),
+    Call(u16),
+    SeByte(usize, u8),
+    SneByte(usize, u8),
+    SeReg(usize, usize),
+    LdByte(usize, u8),
+    AddByte(usize, u8),
+    LdReg(usize, usize),
+    Or(usize, usize),
+    And(usize, usize),
+    Xor(usize, usize),
+    AddReg(usize, usize),
+    SubReg(usize, usize),
+    Shr(usize, usize),
+    Subn(usize, usize),
+    Shl(usize, usize),
+    SneReg(usize, usize),
+    LdI(u16),
+    JpV0(u16),
+    Rnd(usize, u8),
+    Drw(usize, usize, u8),
+    Skp(usize),
+    Sknp(usize),
+    LdVxDt(usize),
+    LdVxK(usize),
+    LdDtVx(usize),
+    LdStVx(usize),
+    AddIVx(usize),
+    LdFVx(usize),
+    LdBVx(usize),
+    LdIVx(usize), // Fx55 - LD [I], Vx (writes memory, may self-modify)
+    LdVxI(usize), // Fx65 - LD Vx, [I]
+    Unknown(u16),
+}
+
+pub fn decode(opcode: u16) -> DecodedOp {
+    let x = ((opcode & 0x0F00) >> 8) as usize;
+    let y = ((opcode & 0x00F0) >> 4) as usize;
+    let nnn = opcode & 0x0FFF;
+    let kk = (opcode & 0x00FF) as u8;
+    let n = (opcode & 0x000F) as u8;
+
+    match opcode & 0xF000 {
+        0x0000 => match opcode {
+            0x00E0 => DecodedOp::Cls,
+            0x00EE => DecodedOp::Ret,
+            _ => DecodedOp::Sys,
+        },
+        0x1000 => DecodedOp::Jp(nnn),
+        0x2000 => DecodedOp::Call(nnn),
+        0x3000 => DecodedOp::SeByte(x, kk),
+        0x4000 => DecodedOp::SneByte(x, kk),
+        0x5000 => DecodedOp::SeReg(x, y),
+        0x6000 => DecodedOp::LdByte(x, kk),
+        0x7000 => DecodedOp::AddByte(x, kk),
+        0x8000 => match n {
+            0x0 => DecodedOp::LdReg(x, y),
+            0x1 => DecodedOp::Or(x, y),
+            0x2 => DecodedOp::And(x, y),
+            0x3 => DecodedOp::Xor(x, y),
+            0x4 => DecodedOp::AddReg(x, y),
+            0x5 => DecodedOp::SubReg(x, y),
+            0x6 => DecodedOp::Shr(x, y),
+            0x7 => DecodedOp::Subn(x, y),
+            0xE => DecodedOp::Shl(x, y),
+            _ => DecodedOp::Unknown(opcode),
+        },
+        0x9000 => DecodedOp::SneReg(x, y),
+        0xA000 => DecodedOp::LdI(nnn),
+        0xB000 => DecodedOp::JpV0(nnn),
+        0xC000 => DecodedOp::Rnd(x, kk),
+        0xD000 => DecodedOp::Drw(x, y, n),
+        0xE000 => match kk {
+            0x9E => DecodedOp::Skp(x),
+            0xA1 => DecodedOp::Sknp(x),
+            _ => DecodedOp::Unknown(opcode),
+        },
+        0xF000 => match kk {
+            0x07 => DecodedOp::LdVxDt(x),
+            0x0A => DecodedOp::LdVxK(x),
+            0x15 => DecodedOp::LdDtVx(x),
+            0x18 => DecodedOp::LdStVx(x),
+            0x1E => DecodedOp::AddIVx(x),
+            0x29 => DecodedOp::LdFVx(x),
+            0x33 => DecodedOp::LdBVx(x),
+            0x55 => DecodedOp::LdIVx(x),
+            0x65 => DecodedOp::LdVxI(x),
+            _ => DecodedOp::Unknown(opcode),
+        },
+        _ => DecodedOp::Unknown(opcode),
+    }
+}
@@ -0,0 +1,586 @@
+use crate::block_cache::{decode, DecodedOp};
+#[cfg(feature = "block_cache")]
+use std::collections::HashMap;
+
+// The standard CHIP-8 font set. Each digit is 5 bytes tall and is loaded into
+// the reserved low memory region (0x000-0x050) so that Fx29 (LD F, Vx) can
+// point I at it.
+const FONT_SET: [u8; 80] = [
+    0xF0, 0x90, 0x90, 0x90, 0xF0, // 0
+    0x20, 0x60, 0x20, 0x20, 0x70, // 1
+    0xF0, 0x10, 0xF0, 0x80, 0xF0, // 2
+    0xF0, 0x10, 0xF0, 0x10, 0xF0, // 3
+    0x90, 0x90, 0xF0, 0x10, 0x10, // 4
+    0xF0, 0x80, 0xF0, 0x10, 0xF0, // 5
+    0xF0, 0x80, 0xF0, 0x90, 0xF0, // 6
+    0xF0, 0x10, 0x20, 0x40, 0x40, // 7
+    0xF0, 0x90, 0xF0, 0x90, 0xF0, // 8
+    0xF0, 0x90, 0xF0, 0x10, 0xF0, // 9
+    0xF0, 0x90, 0xF0, 0x90, 0x90, // A
+    0xE0, 0x90, 0xE0, 0x90, 0xE0, // B
+    0xF0, 0x80, 0x80, 0x80, 0xF0, // C
+    0xE0, 0x90, 0x90, 0x90, 0xE0, // D
+    0xF0, 0x80, 0xF0, 0x80, 0xF0, // E
+    0xF0, 0x80, 0xF0, 0x80, 0x80, // F
+];
+
+pub struct CpuError {
+    pub message: String,
+}
+
+pub struct Cpu {
+    pub memory: [u8; 4096],
+    pub pc: u16, // Program counter starts at memory index 512 (0x200 in hex)
+    pub v: [u8; 16],
+    pub i: u16,
+    pub stack: [u16; 16],
+    pub sp: u8,
+    pub display: [u8; 2048], // 64 x 32
+    pub dt: u8, // delay timer
+    pub st: u8, // sound timer
+    pub keys: [bool; 16],
+    pub rng_state: u32, // xorshift32 state for the Cxkk (RND) opcode
+    pub draw_flag: bool, // set by CLS/DRW, cleared once the frontend repaints
+    // Per-address decode memo: `None` until first used. `HashMap::new()`
+    // isn't a const fn, so this can't be eagerly built in a `const`/`static`
+    // context (the wasm crate's global `Cpu` is one); lazily creating it on
+    // first access keeps the feature usable there too.
+    #[cfg(feature = "block_cache")]
+    pub block_cache: Option<HashMap<u16, DecodedOp>>,
+}
+
+impl Cpu {
+    // memory + v + i + pc + stack + sp + display + dt + st + keys
+    const STATE_SIZE: usize = 4096 + 16 + 2 + 2 + (16 * 2) + 1 + 2048 + 1 + 1 + 16;
+
+    pub fn initialize(&mut self) {
+        self.pc = 0x200;
+        self.i = 0;
+        self.sp = 0;
+        self.v = [0; 16];
+        self.stack = [0; 16];
+        self.display = [0; 2048];
+        self.dt = 0;
+        self.st = 0;
+        self.keys = [false; 16];
+        self.memory = [0; 4096];
+        self.memory[0..FONT_SET.len()].copy_from_slice(&FONT_SET);
+        self.draw_flag = true;
+        #[cfg(feature = "block_cache")]
+        {
+            self.block_cache = None;
+        }
+    }
+
+    /// Whether the sound timer is active. The JS side should keep an
+    /// OscillatorNode/Web Audio beep running for as long as this is true.
+    pub fn sound_active(&self) -> bool {
+        self.st > 0
+    }
+
+    /// Whether the display has changed since the last repaint. The frontend
+    /// should skip its canvas redraw when this returns false, and call
+    /// `clear_draw_flag` once it has painted.
+    pub fn needs_redraw(&self) -> bool {
+        self.draw_flag
+    }
+
+    pub fn clear_draw_flag(&mut self) {
+        self.draw_flag = false;
+    }
+
+    /// Serializes the full machine state into a fixed binary layout (u16s
+    /// little-endian) so the frontend can stash it in localStorage and
+    /// restore it later with `load_state`.
+    ///
+    /// Layout: memory[4096], v[16], i(u16), pc(u16), stack[16](u16 each),
+    /// sp, display[2048], dt, st, keys[16] (one byte each).
+    pub fn save_state(&self) -> Vec<u8> {
+        let mut out = Vec::with_capacity(Self::STATE_SIZE);
+
+        out.extend_from_slice(&self.memory);
+        out.extend_from_slice(&self.v);
+        out.extend_from_slice(&self.i.to_le_bytes());
+        out.extend_from_slice(&self.pc.to_le_bytes());
+        for slot in self.stack.iter() {
+            out.extend_from_slice(&slot.to_le_bytes());
+        }
+        out.push(self.sp);
+        out.extend_from_slice(&self.display);
+        out.push(self.dt);
+        out.push(self.st);
+        for key in self.keys.iter() {
+            out.push(*key as u8);
+        }
+
+        out
+    }
+
+    /// Restores state previously produced by `save_state`. Returns an error
+    /// if `data` isn't exactly `STATE_SIZE` bytes.
+    pub fn load_state(&mut self, data: &[u8]) -> Result<(), CpuError> {
+        if data.len() != Self::STATE_SIZE {
+            return Err(CpuError {
+                message: format!(
+                    "save state is {} bytes, expected {}",
+                    data.len(),
+                    Self::STATE_SIZE
+                ),
+            });
+        }
+
+        let mut offset = 0;
+
+        self.memory.copy_from_slice(&data[offset..offset + 4096]);
+        offset += 4096;
+
+        self.v.copy_from_slice(&data[offset..offset + 16]);
+        offset += 16;
+
+        self.i = u16::from_le_bytes([data[offset], data[offset + 1]]);
+        offset += 2;
+
+        self.pc = u16::from_le_bytes([data[offset], data[offset + 1]]);
+        offset += 2;
+
+        for slot in self.stack.iter_mut() {
+            *slot = u16::from_le_bytes([data[offset], data[offset + 1]]);
+            offset += 2;
+        }
+
+        self.sp = data[offset];
+        offset += 1;
+
+        self.display.copy_from_slice(&data[offset..offset + 2048]);
+        offset += 2048;
+
+        self.dt = data[offset];
+        offset += 1;
+
+        self.st = data[offset];
+        offset += 1;
+
+        for (idx, key) in self.keys.iter_mut().enumerate() {
+            *key = data[offset + idx] != 0;
+        }
+
+        // The restored display won't get painted otherwise: the frontend
+        // skips draw_canvas whenever needs_redraw() is false, which is the
+        // common case right after a paint, so a quickload would show a
+        // stale screen until the next CLS/DRW.
+        self.draw_flag = true;
+
+        #[cfg(feature = "block_cache")]
+        {
+            self.block_cache = None;
+        }
+
+        Ok(())
+    }
+
+    /// Seeds the xorshift32 PRNG backing the Cxkk (RND) opcode. A zero seed
+    /// would get the generator stuck, so it's nudged to a fixed non-zero
+    /// value instead.
+    pub fn seed_rng(&mut self, seed: u32) {
+        self.rng_state = if seed == 0 { 0xDEAD_BEEF } else { seed };
+    }
+
+    fn next_random_byte(&mut self) -> u8 {
+        let mut x = self.rng_state;
+        x ^= x << 13;
+        x ^= x >> 17;
+        x ^= x << 5;
+        self.rng_state = x;
+        (x & 0xFF) as u8
+    }
+
+    pub fn load_game(&mut self, data: Vec<u8>) {
+        for (idx, byte) in data.iter().enumerate() {
+            self.memory[0x200 + idx] = *byte;
+        }
+        #[cfg(feature = "block_cache")]
+        {
+            self.block_cache = None;
+        }
+    }
+
+    #[cfg(not(feature = "block_cache"))]
+    pub fn emulate_cycle(&mut self) -> Result<(), CpuError> {
+        let opcode = self.fetch_opcode();
+        self.execute_opcode(opcode)?;
+        self.tick_timers();
+        Ok(())
+    }
+
+    // Same behavior and the same one-instruction-per-call granularity as the
+    // plain interpreter above, but the decode of each address is memoized so
+    // revisiting it (loops are most of a CHIP-8 program's runtime) replays
+    // the cached `DecodedOp` instead of re-fetching/re-masking the opcode
+    // bits. Kept side by side with the interpreter (toggled by the
+    // `block_cache` feature) so the two can be compared for correctness.
+    #[cfg(feature = "block_cache")]
+    pub fn emulate_cycle(&mut self) -> Result<(), CpuError> {
+        let pc = self.pc;
+
+        // DecodedOp is Copy, so reading the cached entry out is just a
+        // cheap stack copy, not the per-cycle heap allocation a clone of a
+        // larger structure would be.
+        let op = match self.block_cache.get_or_insert_with(HashMap::new).get(&pc) {
+            Some(op) => *op,
+            None => {
+                let decoded = decode(self.fetch_opcode_at(pc));
+                self.block_cache.get_or_insert_with(HashMap::new).insert(pc, decoded);
+                decoded
+            }
+        };
+
+        self.apply_decoded(&op)?;
+        self.tick_timers();
+
+        Ok(())
+    }
+
+    #[cfg(feature = "block_cache")]
+    fn fetch_opcode_at(&self, pc: u16) -> u16 {
+        let code1 = self.memory[pc as usize] as u16;
+        let code2 = self.memory[pc as usize + 1] as u16;
+        code1 << 8 | code2
+    }
+
+    fn tick_timers(&mut self) {
+        if self.dt > 0 {
+            self.dt -= 1;
+        }
+        if self.st > 0 {
+            self.st -= 1;
+        }
+    }
+
+    #[cfg(not(feature = "block_cache"))]
+    fn fetch_opcode(&self) -> u16 {
+        let code1 = self.memory[self.pc as usize] as u16;
+        let code2 = self.memory[(self.pc + 1) as usize] as u16;
+        code1 << 8 | code2
+    }
+
+    #[cfg(not(feature = "block_cache"))]
+    fn execute_opcode(&mut self, opcode: u16) -> Result<(), CpuError> {
+        let decoded = decode(opcode);
+        self.apply_decoded(&decoded)
+    }
+
+    // Every branch is responsible for advancing pc itself, since several
+    // opcodes (skips, jumps, calls) need to move it by something other than
+    // the usual two bytes.
+    fn apply_decoded(&mut self, op: &DecodedOp) -> Result<(), CpuError> {
+        match *op {
+            DecodedOp::Cls => {
+                self.display = [0; 2048];
+                self.draw_flag = true;
+                self.pc += 2;
+            }
+            DecodedOp::Ret => {
+                self.sp -= 1;
+                self.pc = self.stack[self.sp as usize];
+                self.pc += 2;
+            }
+            DecodedOp::Sys => {
+                // 0nnn - SYS addr, ignored by modern interpreters.
+                self.pc += 2;
+            }
+            DecodedOp::Jp(nnn) => {
+                self.pc = nnn;
+            }
+            DecodedOp::Call(nnn) => {
+                self.stack[self.sp as usize] = self.pc;
+                self.sp += 1;
+                self.pc = nnn;
+            }
+            DecodedOp::SeByte(x, kk) => {
+                self.pc += if self.v[x] == kk { 4 } else { 2 };
+            }
+            DecodedOp::SneByte(x, kk) => {
+                self.pc += if self.v[x] != kk { 4 } else { 2 };
+            }
+            DecodedOp::SeReg(x, y) => {
+                self.pc += if self.v[x] == self.v[y] { 4 } else { 2 };
+            }
+            DecodedOp::LdByte(x, kk) => {
+                self.v[x] = kk;
+                self.pc += 2;
+            }
+            DecodedOp::AddByte(x, kk) => {
+                self.v[x] = self.v[x].wrapping_add(kk);
+                self.pc += 2;
+            }
+            DecodedOp::LdReg(x, y) => {
+                self.v[x] = self.v[y];
+                self.pc += 2;
+            }
+            DecodedOp::Or(x, y) => {
+                self.v[x] |= self.v[y];
+                self.pc += 2;
+            }
+            DecodedOp::And(x, y) => {
+                self.v[x] &= self.v[y];
+                self.pc += 2;
+            }
+            DecodedOp::Xor(x, y) => {
+                self.v[x] ^= self.v[y];
+                self.pc += 2;
+            }
+            DecodedOp::AddReg(x, y) => {
+                let (result, carry) = self.v[x].overflowing_add(self.v[y]);
+                self.v[x] = result;
+                self.v[0xF] = carry as u8;
+                self.pc += 2;
+            }
+            DecodedOp::SubReg(x, y) => {
+                let (result, borrow) = self.v[x].overflowing_sub(self.v[y]);
+                self.v[x] = result;
+                self.v[0xF] = !borrow as u8;
+                self.pc += 2;
+            }
+            DecodedOp::Shr(x, _y) => {
+                self.v[0xF] = self.v[x] & 0x1;
+                self.v[x] >>= 1;
+                self.pc += 2;
+            }
+            DecodedOp::Subn(x, y) => {
+                let (result, borrow) = self.v[y].overflowing_sub(self.v[x]);
+                self.v[x] = result;
+                self.v[0xF] = !borrow as u8;
+                self.pc += 2;
+            }
+            DecodedOp::Shl(x, _y) => {
+                self.v[0xF] = (self.v[x] & 0x80) >> 7;
+                self.v[x] <<= 1;
+                self.pc += 2;
+            }
+            DecodedOp::SneReg(x, y) => {
+                self.pc += if self.v[x] != self.v[y] { 4 } else { 2 };
+            }
+            DecodedOp::LdI(nnn) => {
+                self.i = nnn;
+                self.pc += 2;
+            }
+            DecodedOp::JpV0(nnn) => {
+                self.pc = nnn + self.v[0] as u16;
+            }
+            DecodedOp::Rnd(x, kk) => {
+                self.v[x] = self.next_random_byte() & kk;
+                self.pc += 2;
+            }
+            DecodedOp::Drw(x, y, n) => {
+                self.v[0xF] = 0;
+                self.draw_flag = true;
+                let vx = self.v[x] as usize;
+                let vy = self.v[y] as usize;
+
+                for row in 0..n as usize {
+                    let sprite_byte = self.memory[self.i as usize + row];
+                    for col in 0..8 {
+                        if sprite_byte & (0x80 >> col) != 0 {
+                            let px = (vx + col) % 64;
+                            let py = (vy + row) % 32;
+                            let idx = py * 64 + px;
+
+                            if self.display[idx] == 1 {
+                                self.v[0xF] = 1;
+                            }
+                            self.display[idx] ^= 1;
+                        }
+                    }
+                }
+                self.pc += 2;
+            }
+            DecodedOp::Skp(x) => {
+                self.pc += if self.keys[self.v[x] as usize] { 4 } else { 2 };
+            }
+            DecodedOp::Sknp(x) => {
+                self.pc += if !self.keys[self.v[x] as usize] { 4 } else { 2 };
+            }
+            DecodedOp::LdVxDt(x) => {
+                self.v[x] = self.dt;
+                self.pc += 2;
+            }
+            DecodedOp::LdVxK(x) => {
+                // Block until a key is pressed: don't advance pc, try again
+                // next cycle.
+                if let Some(key) = self.keys.iter().position(|&pressed| pressed) {
+                    self.v[x] = key as u8;
+                    self.pc += 2;
+                }
+            }
+            DecodedOp::LdDtVx(x) => {
+                self.dt = self.v[x];
+                self.pc += 2;
+            }
+            DecodedOp::LdStVx(x) => {
+                self.st = self.v[x];
+                self.pc += 2;
+            }
+            DecodedOp::AddIVx(x) => {
+                self.i += self.v[x] as u16;
+                self.pc += 2;
+            }
+            DecodedOp::LdFVx(x) => {
+                self.i = self.v[x] as u16 * 5;
+                self.pc += 2;
+            }
+            DecodedOp::LdBVx(x) => {
+                let value = self.v[x];
+                self.memory[self.i as usize] = value / 100;
+                self.memory[self.i as usize + 1] = (value / 10) % 10;
+                self.memory[self.i as usize + 2] = value % 10;
+                self.pc += 2;
+                self.invalidate_blocks_in(self.i, self.i + 2);
+            }
+            DecodedOp::LdIVx(x) => {
+                for reg in 0..=x {
+                    self.memory[self.i as usize + reg] = self.v[reg];
+                }
+                self.pc += 2;
+                self.invalidate_blocks_in(self.i, self.i + x as u16);
+            }
+            DecodedOp::LdVxI(x) => {
+                for reg in 0..=x {
+                    self.v[reg] = self.memory[self.i as usize + reg];
+                }
+                self.pc += 2;
+            }
+            DecodedOp::Unknown(opcode) => {
+                return Err(CpuError {
+                    message: format!("Unknown opcode: 0x{:X}", opcode),
+                })
+            }
+        }
+
+        Ok(())
+    }
+
+    #[cfg(not(feature = "block_cache"))]
+    fn invalidate_blocks_in(&mut self, _start: u16, _end: u16) {}
+
+    // Self-modifying code (Fx55/Fx33 writing into a cached address) would
+    // otherwise leave a stale decode sitting in the cache. `start`/`end` is
+    // the inclusive byte range that was just written; any cached opcode
+    // whose two bytes overlap it is dropped so the next visit re-decodes
+    // from the rewritten memory.
+    #[cfg(feature = "block_cache")]
+    fn invalidate_blocks_in(&mut self, start: u16, end: u16) {
+        if let Some(cache) = self.block_cache.as_mut() {
+            cache.retain(|&addr, _| addr > end || addr + 1 < start);
+        }
+    }
+}
+
+#[cfg(all(test, feature = "block_cache"))]
+mod tests {
+    use super::Cpu;
+
+    fn blank_cpu() -> Cpu {
+        let mut cpu = Cpu {
+            memory: [0; 4096],
+            pc: 0x200,
+            v: [0; 16],
+            i: 0,
+            stack: [0; 16],
+            sp: 0,
+            display: [0; 2048],
+            dt: 0,
+            st: 0,
+            keys: [false; 16],
+            rng_state: 0x2545_F491,
+            draw_flag: true,
+            block_cache: None,
+        };
+        cpu.initialize();
+        cpu
+    }
+
+    fn run_cycles(cpu: &mut Cpu, n: usize) {
+        for _ in 0..n {
+            assert!(cpu.emulate_cycle().is_ok());
+        }
+    }
+
+    // emulate_cycle must advance exactly one instruction, same as the
+    // interpreter path, so the two can be stepped in lockstep for
+    // correctness comparison and a caller ticking N cycles/frame gets the
+    // same emulated speed regardless of which path is compiled in.
+    #[test]
+    fn emulate_cycle_advances_one_instruction_at_a_time() {
+        let mut cpu = blank_cpu();
+        let program: [u8; 4] = [
+            0x60, 0x05, // 0x200: LD V0, 0x05
+            0x61, 0x06, // 0x202: LD V1, 0x06
+        ];
+        cpu.load_game(program.to_vec());
+
+        assert!(cpu.emulate_cycle().is_ok());
+        assert_eq!(cpu.v[0], 0x05);
+        assert_eq!(cpu.v[1], 0, "second instruction must not run in the same cycle");
+        assert_eq!(cpu.pc, 0x202);
+
+        assert!(cpu.emulate_cycle().is_ok());
+        assert_eq!(cpu.v[1], 0x06);
+        assert_eq!(cpu.pc, 0x204);
+    }
+
+    // Fx55 writing over a previously-cached instruction (self-modifying
+    // code) must invalidate that cache entry, so revisiting the address
+    // later re-decodes the rewritten bytes instead of replaying the stale
+    // decode from before the write.
+    #[test]
+    fn fx55_self_modify_invalidates_cached_decode() {
+        let mut cpu = blank_cpu();
+        let program: [u8; 14] = [
+            0x62, 0x11, // 0x200: LD V2, 0x11 (visited once, then overwritten below)
+            0x60, 0x62, // 0x202: LD V0, 0x62 (high byte of the new opcode at 0x200)
+            0x61, 0x22, // 0x204: LD V1, 0x22 (low byte of the new opcode at 0x200)
+            0xA2, 0x00, // 0x206: LD I, 0x200
+            0xF1, 0x55, // 0x208: LD [I], V1 (writes V0, V1 into memory[0x200..=0x201])
+            0x62, 0x00, // 0x20A: LD V2, 0x00 (reset sentinel before revisiting 0x200)
+            0x12, 0x00, // 0x20C: JP 0x200
+        ];
+        cpu.load_game(program.to_vec());
+
+        run_cycles(&mut cpu, 7); // everything up to and including the JP
+        assert_eq!(cpu.v[2], 0, "sentinel reset before the self-modified address is revisited");
+
+        run_cycles(&mut cpu, 1); // re-decode memory[0x200..] after the rewrite
+        assert_eq!(
+            cpu.v[2], 0x22,
+            "0x200 must re-decode as the rewritten LD V2, 0x22, not replay the cached LD V2, 0x11"
+        );
+        assert_eq!(cpu.pc, 0x202);
+    }
+
+    // Same as above but for Fx33 (LD B, Vx): its BCD write can just as
+    // easily land on a previously-cached address.
+    #[test]
+    fn fx33_self_modify_invalidates_cached_decode() {
+        let mut cpu = blank_cpu();
+        let program: [u8; 12] = [
+            0x61, 0x99, // 0x200: LD V1, 0x99 (visited once, then overwritten below)
+            0x60, 0x05, // 0x202: LD V0, 0x05 (BCD digits of 5 are 0, 0, 5)
+            0xA2, 0x00, // 0x204: LD I, 0x200
+            0xF0, 0x33, // 0x206: LD B, V0 (writes 0, 0, 5 into memory[0x200..=0x202])
+            0x61, 0x00, // 0x208: LD V1, 0x00 (reset sentinel before revisiting 0x200)
+            0x12, 0x00, // 0x20A: JP 0x200
+        ];
+        cpu.load_game(program.to_vec());
+
+        run_cycles(&mut cpu, 6); // everything up to and including the JP
+        assert_eq!(cpu.v[1], 0, "sentinel reset before the self-modified address is revisited");
+
+        run_cycles(&mut cpu, 1); // re-decode memory[0x200..] after the rewrite
+        assert_eq!(
+            cpu.v[1], 0,
+            "0x200 must re-decode as the rewritten (now SYS/ignored) opcode, not replay the cached LD V1, 0x99"
+        );
+        assert_eq!(&cpu.memory[0x200..0x203], &[0, 0, 5]);
+        assert_eq!(cpu.pc, 0x202);
+    }
+}
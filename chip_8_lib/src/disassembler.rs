@@ -127,7 +127,7 @@ pub fn disassemble(opcode: u16) -> String {
                     // 8xyE - SHL Vx {, Vy}
                     // Set Vx = Vx SHL 1.
                     // If the most-significant bit of Vx is 1, then VF is set to 1, otherwise to 0. Then Vx is multiplied by 2.
-                    format!("HL V{} V{} ", x, y)
+                    format!("SHL V{} V{} ", x, y)
                 }
                 _ => {
                     format!("??? {:X}", opcode)
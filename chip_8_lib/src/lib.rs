@@ -0,0 +1,4 @@
+pub mod assembler;
+pub mod block_cache;
+pub mod cpu;
+pub mod disassembler;
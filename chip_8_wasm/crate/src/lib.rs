@@ -1,6 +1,7 @@
 use wasm_bindgen::prelude::*;
 use web_sys::console;
 use js_sys::DataView;
+use chip_8_lib::assembler;
 use chip_8_lib::cpu::Cpu;
 use chip_8_lib::disassembler;
 
@@ -43,13 +44,26 @@ static mut CPU: Cpu = Cpu {
     sp: 0,
     display: [0; 2048],
     dt: 0,
-    keys: [false; 16]
+    st: 0,
+    keys: [false; 16],
+    rng_state: 0x2545_F491,
+    draw_flag: true,
+    #[cfg(feature = "block_cache")]
+    block_cache: None,
 };
 
 #[wasm_bindgen]
 pub fn init() {
     unsafe {
         CPU.initialize();
+        CPU.seed_rng((js_sys::Math::random() * (u32::MAX as f64)) as u32);
+    }
+}
+
+#[wasm_bindgen]
+pub fn seed_rng(seed: f64) {
+    unsafe {
+        CPU.seed_rng(seed as u32);
     }
 }
 
@@ -94,11 +108,55 @@ pub fn update_ui() {
 
     let misc_element = document.get_element_by_id("misc").unwrap();
     unsafe {
-        misc_element.set_inner_html(format!("PC: {} - 0x{:X} <br />DT: {}", CPU.pc, CPU.pc, CPU.dt).as_str());
+        misc_element.set_inner_html(format!("PC: {} - 0x{:X} <br />DT: {} <br />ST: {}", CPU.pc, CPU.pc, CPU.dt, CPU.st).as_str());
     }
 
 }
 
+#[wasm_bindgen]
+pub fn sound_active() -> bool {
+    unsafe { CPU.sound_active() }
+}
+
+// The JS frame loop should check this before calling draw_canvas and skip the
+// repaint when it's false, avoiding a full put_image_data on every frame.
+#[wasm_bindgen]
+pub fn needs_redraw() -> bool {
+    unsafe { CPU.needs_redraw() }
+}
+
+#[wasm_bindgen]
+pub fn save_state() -> Vec<u8> {
+    unsafe { CPU.save_state() }
+}
+
+#[wasm_bindgen]
+pub fn load_state(data: &[u8]) -> Result<(), JsValue> {
+    unsafe {
+        CPU.load_state(data)
+            .map_err(|e| JsValue::from_str(e.message.as_str()))
+    }
+}
+
+// Lets the debugger hand-patch a single instruction: parse a disassembly
+// line like "LD V3 45" back into an opcode and write it into memory at
+// `address` so the edited ROM can be resumed in place.
+#[wasm_bindgen]
+pub fn patch_opcode(address: u16, line: &str) -> Result<(), JsValue> {
+    let opcode = assembler::assemble(line).map_err(|e| JsValue::from_str(&e))?;
+
+    unsafe {
+        CPU.memory[address as usize] = (opcode >> 8) as u8;
+        CPU.memory[address as usize + 1] = (opcode & 0x00FF) as u8;
+        #[cfg(feature = "block_cache")]
+        {
+            CPU.block_cache = None;
+        }
+    }
+
+    Ok(())
+}
+
 #[wasm_bindgen]
 pub fn key_down(key: f64) {
     //console::log_1(&JsValue::from_f64(key));
@@ -168,7 +226,13 @@ pub fn draw_canvas(
 }
 
     let data = ImageData::new_with_u8_clamped_array_and_sh(Clamped(&mut data), width, height)?;
-    ctx.put_image_data(&data, 0.0, 0.0)
+    ctx.put_image_data(&data, 0.0, 0.0)?;
+
+    unsafe {
+        CPU.clear_draw_flag();
+    }
+
+    Ok(())
 }
 
 